@@ -0,0 +1,770 @@
+//! Discover a local R installation and expose what's needed to link against
+//! and build against it, in the style of the `pkg-config` crate: a
+//! [`Config`] builder that [`probe`](Config::probe)s the install and returns
+//! a [`Library`].
+//!
+//! Consumers that just want a build script can call
+//! `r_src::Config::new().probe()` directly. Consumers that want to query R's
+//! configuration programmatically (without emitting `cargo:` metadata) can
+//! disable that via [`Config::cargo_metadata`].
+
+use std::{
+    collections::HashMap,
+    io, env,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Holds key/value pairs parsed from "R CMD config --all".
+#[derive(Debug)]
+struct ConfigVariables {
+    map: HashMap<String, String>,
+}
+
+impl ConfigVariables {
+    fn get_r_cmd_config(&self, key: &str) -> String {
+        self.map.get(key).cloned().unwrap_or_default()
+    }
+}
+
+/// The R interpreter to shell out to when discovering `R_HOME`, honoring an
+/// `R` or `RBINARY` override (in that order) before falling back to `R` on
+/// `PATH`. Mirrors the `RUBY`-env-var pattern used by rutie's build script.
+fn r_executable_for_rhome() -> String {
+    env::var("R")
+        .or_else(|_| env::var("RBINARY"))
+        .unwrap_or_else(|_| "R".to_string())
+}
+
+/// Run `R RHOME` using the given interpreter and return the trimmed stdout.
+fn run_r_rhome(r_executable: &str) -> io::Result<String> {
+    let output = Command::new(r_executable).arg("RHOME").output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "`{} RHOME` failed:\n{}",
+            r_executable,
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// The target triple cargo is building for (e.g. `x86_64-pc-windows-gnu`),
+/// as opposed to the host we happen to be running on.
+fn target_triple() -> String {
+    env::var("TARGET").unwrap_or_default()
+}
+
+/// The `R_HOME_<target>` environment variable name for a given target
+/// triple, e.g. `R_HOME_x86_64_pc_windows_gnu` for
+/// `x86_64-pc-windows-gnu`. Lets a single build configure distinct R
+/// installs per cross-compilation target.
+fn r_home_env_key_for_target(target: &str) -> String {
+    format!("R_HOME_{}", target.replace('-', "_"))
+}
+
+/// Discover R's home directory.
+///
+/// Tries, in order: a per-target `R_HOME_<target>` override, the `R_HOME`
+/// environment variable, then running `R RHOME` (honoring an
+/// `R`/`RBINARY` override for which interpreter to invoke). Panics with a
+/// helpful message if every avenue fails.
+fn get_r_home() -> String {
+    println!("cargo:rerun-if-env-changed=R_HOME");
+    println!("cargo:rerun-if-env-changed=R");
+    println!("cargo:rerun-if-env-changed=RBINARY");
+
+    let per_target_key = r_home_env_key_for_target(&target_triple());
+    println!("cargo:rerun-if-env-changed={}", per_target_key);
+    if let Ok(home) = env::var(&per_target_key) {
+        return home;
+    }
+
+    if let Ok(home) = env::var("R_HOME") {
+        return home;
+    }
+
+    let r_executable = r_executable_for_rhome();
+    run_r_rhome(&r_executable).unwrap_or_else(|e| {
+        panic!(
+            "Error: R_HOME is not set and `{} RHOME` could not be run: {}",
+            r_executable, e
+        );
+    })
+}
+
+/// The OS cargo is building for (`CARGO_CFG_TARGET_OS`), which is what
+/// determines the shape of the R install we need to probe -- not the host
+/// `cfg!(target_os)`, which only describes the machine compiling this
+/// build script and is wrong under cross-compilation.
+fn target_os() -> String {
+    env::var("CARGO_CFG_TARGET_OS").unwrap_or_default()
+}
+
+/// The Windows architecture subdirectory R installs its binaries under,
+/// searched in the order current R releases ship them.
+const WINDOWS_R_ARCHES: &[&str] = &["x64", "i386", "arm64"];
+
+/// Locate the architecture-specific `bin` directory under a Windows
+/// `R_HOME`, e.g. `R_HOME/bin/x64`. Falls back to `R_HOME/bin` if none of
+/// the known architecture subdirectories exist.
+fn windows_r_bin_dir(r_home: &Path) -> PathBuf {
+    for arch in WINDOWS_R_ARCHES {
+        let candidate = r_home.join("bin").join(arch);
+        if candidate.exists() {
+            return candidate;
+        }
+    }
+    r_home.join("bin")
+}
+
+/// Locate the R executable under a given `R_HOME`, for the target we're
+/// building for.
+///
+/// On Windows, R installs the interpreter under an architecture-specific
+/// subdirectory; [`windows_r_bin_dir`] tries each architecture current R
+/// releases ship before falling back to `bin/R.exe` directly.
+fn r_binary_in_home(r_home: &Path) -> PathBuf {
+    if target_os() == "windows" {
+        let candidate = windows_r_bin_dir(r_home).join("R.exe");
+        if candidate.exists() {
+            candidate
+        } else {
+            r_home.join("bin").join("R.exe")
+        }
+    } else {
+        r_home.join("bin").join("R")
+    }
+}
+
+/// Run `R CMD config --all` using the provided R executable path and return its stdout as a String.
+fn r_cmd_config(r_binary: &Path) -> io::Result<String> {
+    let output = Command::new(r_binary)
+        .args(["CMD", "config", "--all"])
+        .output()?;
+    if !output.stderr.is_empty() {
+        println!("> {}", String::from_utf8_lossy(&output.stderr));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Parse `R CMD config --all`'s `KEY=VALUE` lines into a map.
+///
+/// Stops at the first `##` line, since R appends a comment block after the
+/// actual config output.
+fn parse_r_cmd_config_text(configs: &str) -> HashMap<String, String> {
+    let mut rcmd_config_map = HashMap::new();
+    for line in configs.lines() {
+        if line.starts_with("##") {
+            break;
+        }
+        if let Some((key, value)) = line.split_once('=') {
+            rcmd_config_map.insert(key.trim().to_string(), value.trim().to_string());
+        }
+    }
+    rcmd_config_map
+}
+
+/// Invoke `R CMD config --all` and parse its `KEY=VALUE` lines into a map.
+fn parse_r_cmd_configs(r_binary: &Path) -> HashMap<String, String> {
+    let configs = r_cmd_config(r_binary).unwrap_or_default();
+    parse_r_cmd_config_text(&configs)
+}
+
+/// Extract the version number (e.g. `"4.3.1"`) from `R --version`'s first
+/// line, which reads `R version 4.3.1 (2023-06-16) -- ...`.
+fn parse_r_version(version_output: &str) -> Option<String> {
+    let first_line = version_output.lines().next()?;
+    first_line
+        .split_whitespace()
+        .find(|tok| tok.chars().next().is_some_and(|c| c.is_ascii_digit()))
+        .map(|s| s.to_string())
+}
+
+/// Run `R --version` against the given R binary and extract its version
+/// number.
+fn r_version(r_binary: &Path) -> io::Result<String> {
+    let output = Command::new(r_binary).arg("--version").output()?;
+    parse_r_version(&String::from_utf8_lossy(&output.stdout))
+        .ok_or_else(|| io::Error::other("could not parse R version from `R --version` output"))
+}
+
+/// Compare dot-separated numeric version strings (e.g. `"4.3.1"` vs.
+/// `"4.10"`), returning whether `actual >= required`. Missing trailing
+/// components are treated as `0`.
+fn version_at_least(actual: &str, required: &str) -> bool {
+    let actual_parts = actual.split('.').filter_map(|p| p.parse::<u64>().ok());
+    let required_parts = required.split('.').filter_map(|p| p.parse::<u64>().ok());
+    for (a, r) in actual_parts.zip(required_parts) {
+        if a != r {
+            return a > r;
+        }
+    }
+    // Equal on every shared component: longer (more specific) requirement wins.
+    actual.split('.').count() >= required.split('.').count()
+}
+
+/// A digest identifying one "version" of an R install's configuration, so
+/// we can cache the (slow) `R CMD config --all` probe across builds.
+/// Combines the resolved binary's path, its mtime, and its reported
+/// version string -- any of which changing (a toolchain upgrade, a
+/// reinstall) should invalidate the cache.
+fn config_cache_digest(r_binary: &Path) -> io::Result<String> {
+    use std::hash::{Hash, Hasher};
+
+    let mtime = std::fs::metadata(r_binary)?.modified()?;
+    let version = Command::new(r_binary)
+        .arg("--version")
+        .output()
+        .map(|o| String::from_utf8_lossy(&o.stdout).lines().next().unwrap_or("").to_string())
+        .unwrap_or_default();
+
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    r_binary.hash(&mut hasher);
+    mtime.hash(&mut hasher);
+    version.hash(&mut hasher);
+    Ok(format!("{:x}", hasher.finish()))
+}
+
+/// Path of the on-disk cache file for a given digest, under `OUT_DIR`.
+fn config_cache_path(out_dir: &Path, digest: &str) -> PathBuf {
+    out_dir.join(format!("r_cmd_config-{}.cache", digest))
+}
+
+/// Load a previously cached config map, in the simple `KEY=VALUE`-per-line
+/// format we write it in.
+fn load_cached_configs(cache_path: &Path) -> Option<HashMap<String, String>> {
+    let contents = std::fs::read_to_string(cache_path).ok()?;
+    let mut map = HashMap::new();
+    for line in contents.lines() {
+        if let Some((key, value)) = line.split_once('=') {
+            map.insert(key.to_string(), value.to_string());
+        }
+    }
+    Some(map)
+}
+
+/// Write a config map to the on-disk cache in `KEY=VALUE`-per-line format.
+fn store_cached_configs(cache_path: &Path, map: &HashMap<String, String>) -> io::Result<()> {
+    let mut contents = String::new();
+    for (key, value) in map {
+        contents.push_str(key);
+        contents.push('=');
+        contents.push_str(value);
+        contents.push('\n');
+    }
+    std::fs::write(cache_path, contents)
+}
+
+/// Build the configuration map by invoking R commands, caching the parsed
+/// result in `OUT_DIR` so unrelated rebuilds don't re-shell out to R (R's
+/// startup cost dominates the probe). The cache is keyed by
+/// [`config_cache_digest`] and invalidated automatically when that changes.
+fn build_r_cmd_configs(r_home: &Path) -> ConfigVariables {
+    let r_binary = r_binary_in_home(r_home);
+    println!("cargo:rerun-if-changed={}", r_binary.display());
+
+    let out_dir = env::var("OUT_DIR").ok().map(PathBuf::from);
+    let digest = out_dir.as_ref().and_then(|_| config_cache_digest(&r_binary).ok());
+
+    if let (Some(out_dir), Some(digest)) = (&out_dir, &digest) {
+        let cache_path = config_cache_path(out_dir, digest);
+        if let Some(map) = load_cached_configs(&cache_path) {
+            return ConfigVariables { map };
+        }
+
+        let map = parse_r_cmd_configs(&r_binary);
+        let _ = store_cached_configs(&cache_path, &map);
+        return ConfigVariables { map };
+    }
+
+    ConfigVariables {
+        map: parse_r_cmd_configs(&r_binary),
+    }
+}
+
+/// Given a list of strings (such as BLAS, LAPACK, etc. flags),
+/// extract library paths (starting with "-L") and libraries (starting with "-l").
+fn get_libs_and_paths(strings: &[String]) -> (Vec<String>, Vec<String>) {
+    let mut paths = Vec::new();
+    let mut libs = Vec::new();
+    for s in strings {
+        for part in s.split_whitespace() {
+            if let Some(path) = part.strip_prefix("-L") {
+                paths.push(path.to_string());
+            } else if let Some(lib) = part.strip_prefix("-l") {
+                libs.push(lib.to_string());
+            }
+        }
+    }
+    (paths, libs)
+}
+
+/// Given a list of strings (such as CFLAGS, CPPFLAGS, etc.), extract
+/// include directories (starting with "-I") and pass everything else
+/// through unchanged (e.g. "-D" defines) as raw compiler flags.
+fn get_includes_and_cflags(strings: &[String]) -> (Vec<String>, Vec<String>) {
+    let mut includes = Vec::new();
+    let mut flags = Vec::new();
+    for s in strings {
+        for part in s.split_whitespace() {
+            if let Some(dir) = part.strip_prefix("-I") {
+                includes.push(dir.to_string());
+            } else {
+                flags.push(part.to_string());
+            }
+        }
+    }
+    (includes, flags)
+}
+
+/// System libraries that must never be statically linked, even when
+/// `R_ALL_STATIC` is set: they are part of the platform's C runtime and
+/// static archives for them are either unavailable or actively harmful.
+const NEVER_STATIC_LIBS: &[&str] = &["m", "pthread", "dl"];
+
+/// Turn a library name into the form used by its `R_STATIC_*`/`R_DYNAMIC_*`
+/// override (uppercased, `-` replaced with `_`).
+fn env_key_for_lib(lib: &str) -> String {
+    lib.to_uppercase().replace('-', "_")
+}
+
+fn env_flag_set(key: &str) -> bool {
+    env::var(key).map(|v| v != "0").unwrap_or(false)
+}
+
+/// Decide whether `lib` should be linked `"static"` or `"dylib"`.
+///
+/// Checked in precedence order: a per-library `R_STATIC_<LIB>` or
+/// `R_DYNAMIC_<LIB>` override, then the curated [`NEVER_STATIC_LIBS`]
+/// deny list, then the global `R_ALL_STATIC`/`R_ALL_DYNAMIC` switches,
+/// defaulting to dynamic linking.
+fn link_kind_for_lib(lib: &str) -> &'static str {
+    let key = env_key_for_lib(lib);
+    if env_flag_set(&format!("R_STATIC_{}", key)) {
+        return "static";
+    }
+    if env_flag_set(&format!("R_DYNAMIC_{}", key)) {
+        return "dylib";
+    }
+    if NEVER_STATIC_LIBS.contains(&lib) {
+        return "dylib";
+    }
+    if env_flag_set("R_ALL_STATIC") {
+        return "static";
+    }
+    if env_flag_set("R_ALL_DYNAMIC") {
+        return "dylib";
+    }
+    "dylib"
+}
+
+/// R's Windows DLLs that downstream crates commonly need to link against
+/// but which don't ship with a matching MSVC import library.
+const WINDOWS_R_DLLS: &[&str] = &["R.dll", "Rblas.dll", "Rlapack.dll"];
+
+/// Parse the export table out of `dumpbin /exports <dll>` output.
+///
+/// Export rows look like `    1    0 00001000 SymbolName`; we take the
+/// last whitespace-separated column of any row that starts with an
+/// ordinal number.
+fn parse_dumpbin_exports(text: &str) -> Vec<String> {
+    text.lines()
+        .filter_map(|line| {
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            match tokens.as_slice() {
+                [ordinal, _hint, _rva, name] if ordinal.parse::<u32>().is_ok() => {
+                    Some(name.to_string())
+                }
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Run `dumpbin /exports <dll>` and parse its export table.
+fn dumpbin_export_names(dll: &Path) -> io::Result<Vec<String>> {
+    let output = Command::new("dumpbin").arg("/exports").arg(dll).output()?;
+    if !output.status.success() {
+        return Err(io::Error::other("dumpbin /exports failed"));
+    }
+    let names = parse_dumpbin_exports(&String::from_utf8_lossy(&output.stdout));
+    if names.is_empty() {
+        Err(io::Error::other("no exports found in dumpbin output"))
+    } else {
+        Ok(names)
+    }
+}
+
+/// Write a `.def` file listing `exports` for `library_name`.
+fn write_def_file(def_path: &Path, library_name: &str, exports: &[String]) -> io::Result<()> {
+    let mut contents = format!("LIBRARY {}\nEXPORTS\n", library_name);
+    for name in exports {
+        contents.push_str("    ");
+        contents.push_str(name);
+        contents.push('\n');
+    }
+    std::fs::write(def_path, contents)
+}
+
+/// Produce a `.def` file describing `dll`'s exports in `out_dir`, via
+/// `dumpbin /exports` if available, falling back to `gendef` (which
+/// writes the `.def` itself rather than a symbol list we parse).
+fn generate_def_file(dll: &Path, stem: &str, out_dir: &Path) -> io::Result<PathBuf> {
+    let def_path = out_dir.join(format!("{}.def", stem));
+    if let Ok(exports) = dumpbin_export_names(dll) {
+        write_def_file(&def_path, stem, &exports)?;
+        return Ok(def_path);
+    }
+    let status = Command::new("gendef").arg(dll).current_dir(out_dir).status()?;
+    if !status.success() || !def_path.exists() {
+        return Err(io::Error::other(format!(
+            "failed to generate a .def file for {}",
+            dll.display()
+        )));
+    }
+    Ok(def_path)
+}
+
+/// The `/machine:` value MSVC's `lib.exe` expects for the target arch.
+fn msvc_machine_arch() -> &'static str {
+    match env::var("CARGO_CFG_TARGET_ARCH").as_deref() {
+        Ok("x86_64") => "X64",
+        Ok("aarch64") => "ARM64",
+        Ok("x86") => "X86",
+        _ => "X64",
+    }
+}
+
+/// Synthesize an MSVC-compatible `.lib` import library for `dll` in
+/// `out_dir`, since R ships its Windows DLLs without one.
+///
+/// Locates the DLL's exports (via `dumpbin /exports` or `gendef`), writes
+/// a `.def` file, then invokes MSVC's `lib /def:` (or mingw's `dlltool`
+/// as a fallback) to produce the `.lib`.
+fn generate_import_lib(dll: &Path, out_dir: &Path) -> io::Result<PathBuf> {
+    let stem = dll.file_stem().and_then(|s| s.to_str()).unwrap_or("r");
+    let def_path = generate_def_file(dll, stem, out_dir)?;
+    let lib_path = out_dir.join(format!("{}.lib", stem));
+
+    let lib_ok = Command::new("lib")
+        .arg(format!("/def:{}", def_path.display()))
+        .arg(format!("/out:{}", lib_path.display()))
+        .arg(format!("/machine:{}", msvc_machine_arch()))
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false);
+
+    if !lib_ok {
+        Command::new("dlltool")
+            .arg("-d")
+            .arg(&def_path)
+            .arg("-l")
+            .arg(&lib_path)
+            .status()?;
+    }
+
+    Ok(lib_path)
+}
+
+/// For Windows MSVC targets, generate import libraries for R's DLLs (which
+/// ship without one) and return the directory they were written to, so it
+/// can be added to the linker search path.
+fn generate_windows_import_libs(r_home: &Path, out_dir: &Path) -> io::Result<PathBuf> {
+    let bin_dir = windows_r_bin_dir(r_home);
+
+    for dll_name in WINDOWS_R_DLLS {
+        let dll_path = bin_dir.join(dll_name);
+        if dll_path.exists() {
+            generate_import_lib(&dll_path, out_dir)?;
+        }
+    }
+    Ok(out_dir.to_path_buf())
+}
+
+/// The result of a successful [`Config::probe`]: everything a downstream
+/// build needs to link against R's BLAS/LAPACK/Fortran runtime.
+///
+/// Mirrors the `pkg_config::Library` shape so callers already familiar with
+/// that crate feel at home.
+#[derive(Debug, Default, Clone)]
+pub struct Library {
+    /// Directories to add to the linker search path (`-L`).
+    pub link_paths: Vec<String>,
+    /// Library names to link against, without the `-l` prefix.
+    pub libs: Vec<String>,
+    /// Header search directories, gathered from `CFLAGS`/`CPPFLAGS`/`FCFLAGS`
+    /// `-I` entries plus `R_INCLUDE_DIR`.
+    pub include_paths: Vec<String>,
+    /// Raw compiler flags that weren't recognized as `-L`/`-l`/`-I`.
+    pub cflags: Vec<String>,
+    /// Raw linker flags that weren't recognized as `-L`/`-l`.
+    pub ldflags: Vec<String>,
+}
+
+/// Builder for probing the local R installation, in the style of the
+/// `pkg-config` crate's `Config`.
+///
+/// ```no_run
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let library = r_src::Config::new().atleast_r_version("4.0").probe()?;
+/// println!("{:?}", library.libs);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct Config {
+    min_version: Option<String>,
+    cargo_metadata: bool,
+}
+
+impl Config {
+    /// Create a new builder with default settings (`cargo_metadata` on).
+    pub fn new() -> Config {
+        Config {
+            min_version: None,
+            cargo_metadata: true,
+        }
+    }
+
+    /// Require at least this R version (e.g. `"4.0"`). [`probe`](Config::probe)
+    /// fails if the discovered R reports an older version.
+    pub fn atleast_r_version(&mut self, version: &str) -> &mut Config {
+        self.min_version = Some(version.to_string());
+        self
+    }
+
+    /// Toggle whether `probe()` emits `cargo:` metadata lines. Disable this
+    /// when querying R's configuration programmatically rather than from a
+    /// build script.
+    pub fn cargo_metadata(&mut self, emit: bool) -> &mut Config {
+        self.cargo_metadata = emit;
+        self
+    }
+
+    /// Probe the local R installation and return the resulting [`Library`].
+    pub fn probe(&self) -> io::Result<Library> {
+        let r_home = PathBuf::from(get_r_home());
+
+        if let Some(min_version) = &self.min_version {
+            let actual = r_version(&r_binary_in_home(&r_home))?;
+            if !version_at_least(&actual, min_version) {
+                return Err(io::Error::other(format!(
+                    "found R version {}, but at least {} is required",
+                    actual, min_version
+                )));
+            }
+        }
+
+        let r_configs = build_r_cmd_configs(&r_home);
+        let config_strings = [
+            r_configs.get_r_cmd_config("BLAS_LIBS"),
+            r_configs.get_r_cmd_config("LAPACK_LIBS"),
+            r_configs.get_r_cmd_config("FLIBS"),
+        ];
+        let (mut link_paths, libs) = get_libs_and_paths(&config_strings);
+
+        let compile_strings = [
+            r_configs.get_r_cmd_config("CFLAGS"),
+            r_configs.get_r_cmd_config("CPPFLAGS"),
+            r_configs.get_r_cmd_config("FCFLAGS"),
+        ];
+        let (mut include_paths, cflags) = get_includes_and_cflags(&compile_strings);
+        let r_include_dir = r_configs.get_r_cmd_config("R_INCLUDE_DIR");
+        if !r_include_dir.is_empty() {
+            include_paths.push(r_include_dir);
+        }
+
+        let is_msvc = target_os() == "windows" && env::var("CARGO_CFG_TARGET_ENV").as_deref() == Ok("msvc");
+        if is_msvc {
+            if let Ok(out_dir) = env::var("OUT_DIR") {
+                match generate_windows_import_libs(&r_home, Path::new(&out_dir)) {
+                    Ok(import_lib_dir) => {
+                        link_paths.push(import_lib_dir.to_string_lossy().into_owned());
+                    }
+                    Err(e) => {
+                        println!("cargo:warning=failed to generate R import libraries: {}", e);
+                    }
+                }
+            }
+        }
+
+        if self.cargo_metadata {
+            for path in &link_paths {
+                if Path::new(path).exists() {
+                    println!("cargo:rustc-link-search={}", path);
+                    eprintln!("cargo:rustc-link-search={}", path);
+                }
+            }
+            for lib in &libs {
+                let kind = link_kind_for_lib(lib);
+                println!("cargo:rustc-link-lib={}={}", kind, lib);
+                eprintln!("cargo:rustc-link-lib={}={}", kind, lib);
+            }
+            // The `cargo:include=` convention is what bindgen-driven downstream
+            // build scripts look for to find a dependency's headers. Cargo only
+            // keeps the last value for a given metadata key, so all of them have
+            // to be joined into one line (mirroring how the `cc`/`pkg-config`
+            // crates emit their own `DEP_*_INCLUDE`).
+            if !include_paths.is_empty() {
+                if let Some(joined) = env::join_paths(&include_paths)
+                    .ok()
+                    .and_then(|p| p.into_string().ok())
+                {
+                    println!("cargo:include={}", joined);
+                }
+            }
+        }
+
+        Ok(Library {
+            link_paths,
+            libs,
+            include_paths,
+            cflags,
+            ldflags: Vec::new(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    /// `link_kind_for_lib` reads process-global env vars; serialize any test
+    /// that mutates `R_STATIC_*`/`R_DYNAMIC_*`/`R_ALL_STATIC`/`R_ALL_DYNAMIC`
+    /// so they don't race with tests asserting the no-override default.
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn get_libs_and_paths_splits_l_and_big_l_flags() {
+        let input = vec!["-L/usr/lib/R/lib -lR -lm".to_string()];
+        let (paths, libs) = get_libs_and_paths(&input);
+        assert_eq!(paths, vec!["/usr/lib/R/lib".to_string()]);
+        assert_eq!(libs, vec!["R".to_string(), "m".to_string()]);
+    }
+
+    #[test]
+    fn get_libs_and_paths_ignores_unrelated_flags() {
+        let input = vec!["-fopenmp -O2".to_string()];
+        let (paths, libs) = get_libs_and_paths(&input);
+        assert!(paths.is_empty());
+        assert!(libs.is_empty());
+    }
+
+    #[test]
+    fn get_includes_and_cflags_splits_i_from_everything_else() {
+        let input = vec!["-I/usr/include/R -D_FORTIFY_SOURCE=2".to_string()];
+        let (includes, flags) = get_includes_and_cflags(&input);
+        assert_eq!(includes, vec!["/usr/include/R".to_string()]);
+        assert_eq!(flags, vec!["-D_FORTIFY_SOURCE=2".to_string()]);
+    }
+
+    #[test]
+    fn parse_r_cmd_config_text_keeps_values_containing_equals_signs() {
+        let configs = "CPPFLAGS=-I/tmp/x/include2 -D_FORTIFY_SOURCE=2\nCC=gcc\n## comments follow\nJUNK=ignored";
+        let map = parse_r_cmd_config_text(configs);
+        assert_eq!(
+            map.get("CPPFLAGS").map(String::as_str),
+            Some("-I/tmp/x/include2 -D_FORTIFY_SOURCE=2")
+        );
+        assert_eq!(map.get("CC").map(String::as_str), Some("gcc"));
+        assert!(!map.contains_key("JUNK"));
+    }
+
+    #[test]
+    fn parse_r_version_extracts_the_version_number() {
+        let output = "R version 4.3.1 (2023-06-16) -- \"Beagle Scouts\"\nCopyright (C) 2023...";
+        assert_eq!(parse_r_version(output), Some("4.3.1".to_string()));
+    }
+
+    #[test]
+    fn parse_r_version_returns_none_for_unrecognized_output() {
+        assert_eq!(parse_r_version("not an R version line"), None);
+    }
+
+    #[test]
+    fn version_at_least_compares_numeric_components() {
+        assert!(version_at_least("4.3.1", "4.3"));
+        assert!(version_at_least("4.3", "4.3"));
+        assert!(!version_at_least("4.3", "4.3.1"));
+        assert!(!version_at_least("4.9", "4.10"));
+        assert!(version_at_least("5.0", "4.10"));
+    }
+
+    #[test]
+    fn env_key_for_lib_uppercases_and_replaces_dashes() {
+        assert_eq!(env_key_for_lib("lapack-ref"), "LAPACK_REF");
+        assert_eq!(env_key_for_lib("R"), "R");
+    }
+
+    #[test]
+    fn link_kind_for_lib_defaults_to_dylib() {
+        let _guard = ENV_LOCK.lock().unwrap();
+        assert_eq!(link_kind_for_lib("some-unconfigured-test-lib"), "dylib");
+    }
+
+    #[test]
+    fn link_kind_for_lib_never_statically_links_denylisted_libs() {
+        for lib in NEVER_STATIC_LIBS {
+            assert_eq!(link_kind_for_lib(lib), "dylib");
+        }
+    }
+
+    #[test]
+    fn link_kind_for_lib_denylist_beats_r_all_static() {
+        let _guard = ENV_LOCK.lock().unwrap();
+        env::set_var("R_ALL_STATIC", "1");
+        let result = link_kind_for_lib(NEVER_STATIC_LIBS[0]);
+        env::remove_var("R_ALL_STATIC");
+        assert_eq!(result, "dylib");
+    }
+
+    #[test]
+    fn link_kind_for_lib_honors_per_lib_static_override() {
+        let _guard = ENV_LOCK.lock().unwrap();
+        let lib = "r-src-test-static-override-lib";
+        let key = format!("R_STATIC_{}", env_key_for_lib(lib));
+        env::set_var(&key, "1");
+        assert_eq!(link_kind_for_lib(lib), "static");
+        env::remove_var(&key);
+    }
+
+    #[test]
+    fn parse_dumpbin_exports_takes_the_symbol_column() {
+        let text = "\n    ordinal hint RVA      name\n\n          1    0 00001000 R_init\n          2    1 00002000 R_do_something\nsomething that is not an export row\n";
+        let names = parse_dumpbin_exports(text);
+        assert_eq!(names, vec!["R_init".to_string(), "R_do_something".to_string()]);
+    }
+
+    #[test]
+    fn parse_dumpbin_exports_returns_empty_for_no_matches() {
+        assert!(parse_dumpbin_exports("no exports here").is_empty());
+    }
+
+    #[test]
+    fn config_cache_round_trips_through_disk() {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let cache_path = std::env::temp_dir()
+            .join(format!("r_src_test_cache_{}_{}", std::process::id(), n));
+
+        let mut map = HashMap::new();
+        map.insert("BLAS_LIBS".to_string(), "-lopenblas".to_string());
+        map.insert("CPPFLAGS".to_string(), "-I/tmp/x -D_FORTIFY_SOURCE=2".to_string());
+
+        store_cached_configs(&cache_path, &map).unwrap();
+        let loaded = load_cached_configs(&cache_path).unwrap();
+
+        std::fs::remove_file(&cache_path).unwrap();
+        assert_eq!(loaded, map);
+    }
+
+    #[test]
+    fn load_cached_configs_returns_none_for_missing_file() {
+        let missing = std::env::temp_dir().join("r_src_test_cache_does_not_exist");
+        assert!(load_cached_configs(&missing).is_none());
+    }
+}